@@ -0,0 +1,113 @@
+use bevy::math::Isometry3d;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{RapierContextColliders, RapierRigidBodySet};
+use rapier3d::prelude::ShapeType;
+
+use crate::events::UrdfRobotRigidBodyHandle;
+use crate::plugin::rapier_isometry_to_bevy_transform;
+
+const PRIMITIVE_COLLIDER_COLOR: Color = Color::srgb(0.1, 0.9, 0.3);
+const MESH_COLLIDER_COLOR: Color = Color::srgb(0.9, 0.6, 0.1);
+
+/// Toggles the collider wireframes drawn by [`UrdfDebugRenderPlugin`]. Off by
+/// default so it doesn't clutter a scene that isn't being debugged.
+#[derive(Resource)]
+pub struct UrdfDebugRenderSettings {
+    pub enabled: bool,
+}
+
+impl Default for UrdfDebugRenderSettings {
+    fn default() -> Self {
+        Self { enabled: false }
+    }
+}
+
+/// Draws the rapier collider actually attached to each spawned link as a
+/// gizmo wireframe, using the same Z-up -> Y-up conversion as
+/// `sync_robot_geometry`. Lets the `quat_fix` mapping be checked by eye
+/// against the visual mesh it's meant to line up with.
+pub struct UrdfDebugRenderPlugin;
+
+impl Plugin for UrdfDebugRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UrdfDebugRenderSettings>()
+            .add_systems(Update, draw_robot_colliders);
+    }
+}
+
+fn draw_robot_colliders(
+    settings: Res<UrdfDebugRenderSettings>,
+    mut gizmos: Gizmos,
+    q_robot_bodies: Query<&UrdfRobotRigidBodyHandle>,
+    q_rapier_context: Query<(&RapierRigidBodySet, &RapierContextColliders)>,
+) {
+    if !settings.enabled {
+        return;
+    }
+
+    for body_handle in q_robot_bodies.iter() {
+        for (rigid_body_set, collider_set) in q_rapier_context.iter() {
+            let Some(body) = rigid_body_set.bodies.get(body_handle.0) else {
+                continue;
+            };
+
+            for collider_handle in body.colliders() {
+                let Some(collider) = collider_set.colliders.get(*collider_handle) else {
+                    continue;
+                };
+
+                let bevy_transform = rapier_isometry_to_bevy_transform(collider.position());
+                let isometry = Isometry3d::new(bevy_transform.translation, bevy_transform.rotation);
+
+                let shape = collider.shape();
+                match shape.shape_type() {
+                    ShapeType::Cuboid => {
+                        let half_extents = shape.as_cuboid().unwrap().half_extents;
+                        gizmos.primitive_3d(
+                            &Cuboid::new(
+                                half_extents.x * 2.0,
+                                half_extents.y * 2.0,
+                                half_extents.z * 2.0,
+                            ),
+                            isometry,
+                            PRIMITIVE_COLLIDER_COLOR,
+                        );
+                    }
+                    ShapeType::Ball => {
+                        let radius = shape.as_ball().unwrap().radius;
+                        gizmos.primitive_3d(
+                            &Sphere::new(radius),
+                            isometry,
+                            PRIMITIVE_COLLIDER_COLOR,
+                        );
+                    }
+                    ShapeType::Cylinder => {
+                        let cylinder = shape.as_cylinder().unwrap();
+                        gizmos.primitive_3d(
+                            &Cylinder::new(cylinder.radius, cylinder.half_height * 2.0),
+                            isometry,
+                            PRIMITIVE_COLLIDER_COLOR,
+                        );
+                    }
+                    ShapeType::Capsule => {
+                        let capsule = shape.as_capsule().unwrap();
+                        gizmos.primitive_3d(
+                            &Capsule3d::new(capsule.radius, capsule.segment.length()),
+                            isometry,
+                            PRIMITIVE_COLLIDER_COLOR,
+                        );
+                    }
+                    _ => {
+                        let aabb = shape.compute_local_aabb();
+                        let extents = aabb.extents();
+                        gizmos.primitive_3d(
+                            &Cuboid::new(extents.x, extents.y, extents.z),
+                            isometry,
+                            MESH_COLLIDER_COLOR,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -2,11 +2,13 @@ use std::path::Path;
 
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
-use rapier3d::prelude::{MultibodyJointHandle, RigidBodyHandle};
-use rapier3d_urdf::{UrdfMultibodyOptions, UrdfRobotHandles};
+use rapier3d::prelude::{ColliderBuilder, MultibodyJointHandle, RigidBodyHandle};
 
 use crate::{
-    plugin::extract_robot_geometry,
+    plugin::{
+        extract_robot_geometry, rapier_isometry_to_bevy_transform, RapierBackend,
+        RapierInsertContext, UrdfPhysicsBackend,
+    },
     urdf_asset_loader::{RpyAssetLoaderSettings, UrdfAsset},
 };
 
@@ -14,12 +16,16 @@ use crate::{
 pub struct SpawnRobot {
     pub handle: Handle<UrdfAsset>,
     pub mesh_dir: String,
+    /// How to derive physics colliders for mesh-only links that have no
+    /// explicit `<collision>` geometry.
+    pub mesh_collider_shape: ComputedColliderShape,
 }
 
 #[derive(Clone, Event)]
 pub struct WaitRobotLoaded {
     pub handle: Handle<UrdfAsset>,
     pub mesh_dir: String,
+    pub mesh_collider_shape: ComputedColliderShape,
 }
 
 #[derive(Clone, Event)]
@@ -40,6 +46,98 @@ pub struct UrdfRobot {}
 #[derive(Component, Default, Deref)]
 pub struct UrdfRobotRigidBodyHandle(pub RigidBodyHandle);
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UrdfJointKind {
+    Revolute,
+    Continuous,
+    Prismatic,
+    Fixed,
+}
+
+impl From<&urdf_rs::JointType> for UrdfJointKind {
+    fn from(joint_type: &urdf_rs::JointType) -> Self {
+        match joint_type {
+            urdf_rs::JointType::Revolute => UrdfJointKind::Revolute,
+            urdf_rs::JointType::Continuous => UrdfJointKind::Continuous,
+            urdf_rs::JointType::Prismatic => UrdfJointKind::Prismatic,
+            _ => UrdfJointKind::Fixed,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UrdfJointLimits {
+    pub lower: f32,
+    pub upper: f32,
+    pub effort: f32,
+    pub velocity: f32,
+}
+
+/// A joint created by `insert_using_multibody_joints`, attached to the child
+/// link's entity so it can be actuated via [`SetJointMotor`] and read back
+/// through [`JointState`].
+#[derive(Component)]
+pub struct UrdfJoint {
+    pub handle: MultibodyJointHandle,
+    pub name: String,
+    pub kind: UrdfJointKind,
+    pub limits: Option<UrdfJointLimits>,
+    pub axis: Vec3,
+    pub parent_body: RigidBodyHandle,
+    /// `(child_pos - parent_pos).dot(axis)`, with the offset rotated into the
+    /// parent link's frame, at spawn time, i.e. at the
+    /// joint's zero configuration. [`update_joint_state`] subtracts this
+    /// from the same projection at runtime so a prismatic joint's static
+    /// `<joint><origin>` offset doesn't show up as a constant bias in
+    /// [`JointState::position`].
+    pub origin_offset: f32,
+}
+
+/// Rotates a joint's local `<axis>` by its `<origin>` rpy so it's expressed
+/// in the parent link's frame — the frame [`update_joint_state`] projects
+/// `relative_pos`/`relative_rot` into. `<joint><axis>` is given in the joint
+/// frame (coincident with the child at zero configuration), so using it
+/// unrotated only happens to work while every joint's `<origin>` rotation is
+/// identity.
+fn joint_axis_in_parent_frame(joint: &urdf_rs::Joint) -> Vec3 {
+    let axis = Vec3::new(
+        joint.axis.xyz[0] as f32,
+        joint.axis.xyz[1] as f32,
+        joint.axis.xyz[2] as f32,
+    )
+    .normalize_or_zero();
+
+    let [roll, pitch, yaw] = joint.origin.rpy;
+    let origin_rotation = Quat::from_rotation_z(yaw as f32)
+        * Quat::from_rotation_y(pitch as f32)
+        * Quat::from_rotation_x(roll as f32);
+
+    origin_rotation * axis
+}
+
+#[derive(Component, Default)]
+pub struct JointState {
+    pub position: f32,
+    pub velocity: f32,
+}
+
+#[derive(Clone, Event)]
+pub struct SetJointMotor {
+    pub entity: Entity,
+    pub target_pos: f32,
+    pub target_vel: f32,
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+/// Marks a mesh-visual link entity still waiting on its render [`Mesh`] to
+/// finish loading before a collider can be derived from it.
+#[derive(Component)]
+pub struct MeshColliderPending {
+    pub body: RigidBodyHandle,
+    pub compute_shape: ComputedColliderShape,
+}
+
 pub(crate) fn handle_spawn_robot(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -61,35 +159,91 @@ pub(crate) fn handle_spawn_robot(
         let rapier_context_simulation_entity = q_rapier_context_simulation.iter().next().unwrap().0;
         let robot_handle = event.handle.clone();
         if let Some(urdf) = urdf_assets.get(robot_handle.id()) {
-            let mut handles: Option<UrdfRobotHandles<Option<MultibodyJointHandle>>> = None;
-            // let mut handles: Option<UrdfRobotHandles<ImpulseJointHandle>> = None;
+            let mut inserted: Option<(Vec<RigidBodyHandle>, Vec<Option<MultibodyJointHandle>>)> =
+                None;
             for (_entity, mut rigid_body_set, mut collider_set, mut multibidy_joint_set) in
                 q_rapier_context.iter_mut()
             {
-                let urdf_robot = urdf.urdf_robot.clone();
-
-                handles = Some(urdf_robot.clone().insert_using_multibody_joints(
-                    &mut rigid_body_set.bodies,
-                    &mut collider_set.colliders,
-                    &mut multibidy_joint_set.multibody_joints,
-                    UrdfMultibodyOptions::DISABLE_SELF_CONTACTS,
-                ));
+                let mut insert_ctx = RapierInsertContext {
+                    bodies: &mut rigid_body_set.bodies,
+                    colliders: &mut collider_set.colliders,
+                    multibody_joints: &mut multibidy_joint_set.multibody_joints,
+                };
+                inserted = Some(RapierBackend::insert_robot(&mut insert_ctx, urdf));
                 break;
             }
 
-            if handles.is_none() {
+            let Some((body_handles, joint_handles)) = inserted else {
                 panic!("couldn't initialize handles");
-            }
+            };
+            let geoms = extract_robot_geometry(urdf);
 
-            let body_handles: Vec<RigidBodyHandle> = handles
-                .unwrap()
+            assert_eq!(body_handles.len(), geoms.len());
+
+            let link_index_by_name: std::collections::HashMap<&str, usize> = urdf
+                .robot
                 .links
                 .iter()
-                .map(|link| link.body)
+                .enumerate()
+                .map(|(index, link)| (link.name.as_str(), index))
                 .collect();
-            let geoms = extract_robot_geometry(urdf);
 
-            assert_eq!(body_handles.len(), geoms.len());
+            let mut joint_by_child: std::collections::HashMap<usize, UrdfJoint> = urdf
+                .robot
+                .joints
+                .iter()
+                .zip(joint_handles.iter())
+                .filter_map(|(joint, multibody_handle)| {
+                    let child_index = *link_index_by_name.get(joint.child.link.as_str())?;
+                    let parent_index = *link_index_by_name.get(joint.parent.link.as_str())?;
+                    let multibody_handle = (*multibody_handle)?;
+
+                    let limits = match joint.joint_type {
+                        urdf_rs::JointType::Revolute | urdf_rs::JointType::Prismatic => {
+                            Some(UrdfJointLimits {
+                                lower: joint.limit.lower as f32,
+                                upper: joint.limit.upper as f32,
+                                effort: joint.limit.effort as f32,
+                                velocity: joint.limit.velocity as f32,
+                            })
+                        }
+                        _ => None,
+                    };
+
+                    let axis = joint_axis_in_parent_frame(joint);
+
+                    // The joint's zero-configuration offset: parent/child
+                    // link origins are almost never coincident (the
+                    // `<joint><origin>` translation), so a prismatic joint's
+                    // position readback needs to subtract this static
+                    // lever-arm rather than reporting the raw world offset.
+                    let parent_pos = urdf.urdf_robot.links[parent_index].body.position();
+                    let child_pos = urdf.urdf_robot.links[child_index].body.position();
+                    let parent_rot = parent_pos.rotation;
+                    let parent_quat =
+                        Quat::from_array([parent_rot.i, parent_rot.j, parent_rot.k, parent_rot.w]);
+                    let origin_offset = (parent_quat.inverse()
+                        * Vec3::new(
+                            child_pos.translation.x - parent_pos.translation.x,
+                            child_pos.translation.y - parent_pos.translation.y,
+                            child_pos.translation.z - parent_pos.translation.z,
+                        ))
+                    .dot(axis);
+
+                    Some((
+                        child_index,
+                        UrdfJoint {
+                            handle: multibody_handle,
+                            name: joint.name.clone(),
+                            kind: UrdfJointKind::from(&joint.joint_type),
+                            limits,
+                            axis,
+                            parent_body: body_handles[parent_index],
+                            origin_offset,
+                        },
+                    ))
+                })
+                .collect();
 
             commands
                 .spawn((
@@ -99,18 +253,26 @@ pub(crate) fn handle_spawn_robot(
                     InheritedVisibility::VISIBLE,
                 ))
                 .with_children(|children| {
-                    for (index, geom, _inertia_pose, _collider) in geoms {
+                    for (index, geom, _inertia_pose, colliders) in geoms {
                         if geom.is_none() {
                             continue;
                         }
+                        let is_mesh_geometry =
+                            matches!(geom, Some(urdf_rs::Geometry::Mesh { .. }));
+                        let has_explicit_colliders =
+                            !urdf.urdf_robot.links[index].colliders.is_empty();
                         let mesh_3d: Mesh3d = match geom.unwrap() {
                             urdf_rs::Geometry::Box { size } => Mesh3d(meshes.add(Cuboid::new(
                                 size[0] as f32 * 2.0,
                                 size[2] as f32 * 2.0,
                                 size[1] as f32 * 2.0,
                             ))),
-                            urdf_rs::Geometry::Cylinder { radius, length } => todo!(),
-                            urdf_rs::Geometry::Capsule { radius, length } => todo!(),
+                            urdf_rs::Geometry::Cylinder { radius, length } => Mesh3d(
+                                meshes.add(Cylinder::new(radius as f32, length as f32)),
+                            ),
+                            urdf_rs::Geometry::Capsule { radius, length } => Mesh3d(
+                                meshes.add(Capsule3d::new(radius as f32, length as f32)),
+                            ),
                             urdf_rs::Geometry::Sphere { radius } => {
                                 Mesh3d(meshes.add(Sphere::new(radius as f32)))
                             }
@@ -124,41 +286,49 @@ pub(crate) fn handle_spawn_robot(
                         };
 
                         let rapier_link = urdf.urdf_robot.links[index].clone();
-                        let rapier_pos = rapier_link.body.position();
-                        let rapier_rot = rapier_pos.rotation;
-
-                        let quat_fix = Quat::from_rotation_z(std::f32::consts::PI);
-                        let bevy_quat = quat_fix
-                            * Quat::from_array([
-                                rapier_rot.i,
-                                rapier_rot.j,
-                                rapier_rot.k,
-                                rapier_rot.w,
-                            ]);
-
-                        let rapier_vec = Vec3::new(
-                            rapier_pos.translation.x,
-                            rapier_pos.translation.y,
-                            rapier_pos.translation.z,
-                        );
-                        let bevy_vec = quat_fix.mul_vec3(rapier_vec);
-
-                        let transform =
-                            Transform::from_translation(bevy_vec).with_rotation(bevy_quat);
-
-                        children.spawn((
+                        let transform = rapier_isometry_to_bevy_transform(rapier_link.body.position());
+
+                        let mut link_entity = children.spawn((
                             mesh_3d,
                             MeshMaterial3d(materials.add(Color::srgb(0.3, 0.4, 0.3))),
                             UrdfRobotRigidBodyHandle(body_handles[index]),
                             RapierContextEntityLink(rapier_context_simulation_entity),
                             transform,
                         ));
+
+                        if let Some(urdf_joint) = joint_by_child.remove(&index) {
+                            link_entity.insert((urdf_joint, JointState::default()));
+                        }
+
+                        if is_mesh_geometry && colliders.is_empty() {
+                            link_entity.insert(MeshColliderPending {
+                                body: body_handles[index],
+                                compute_shape: event.mesh_collider_shape.clone(),
+                            });
+                        } else if !has_explicit_colliders {
+                            // These were synthesized from the visual primitive
+                            // (no <collision> element), so rapier3d_urdf never
+                            // saw them and attaching is on us.
+                            for (_entity, mut rigid_body_set, mut collider_set, _) in
+                                q_rapier_context.iter_mut()
+                            {
+                                for collider in colliders {
+                                    collider_set.colliders.insert_with_parent(
+                                        collider,
+                                        body_handles[index],
+                                        &mut rigid_body_set.bodies,
+                                    );
+                                }
+                                break;
+                            }
+                        }
                     }
                 });
         } else {
             ew_wait_robot_loaded.send(WaitRobotLoaded {
                 handle: event.handle.clone(),
                 mesh_dir: event.mesh_dir.clone(),
+                mesh_collider_shape: event.mesh_collider_shape.clone(),
             });
         }
     }
@@ -192,6 +362,134 @@ pub(crate) fn handle_wait_robot_loaded(
         ew_spawn_robot.send(SpawnRobot {
             handle: event.handle.clone(),
             mesh_dir: event.mesh_dir.clone(),
+            mesh_collider_shape: event.mesh_collider_shape.clone(),
         });
     }
 }
+
+pub(crate) fn handle_set_joint_motor(
+    mut er_set_joint_motor: EventReader<SetJointMotor>,
+    q_urdf_joint: Query<&UrdfJoint>,
+    mut q_rapier_context_joints: Query<&mut RapierContextJoints>,
+) {
+    for event in er_set_joint_motor.read() {
+        let Ok(urdf_joint) = q_urdf_joint.get(event.entity) else {
+            continue;
+        };
+
+        let axis = match urdf_joint.kind {
+            UrdfJointKind::Revolute | UrdfJointKind::Continuous => JointAxis::AngX,
+            UrdfJointKind::Prismatic => JointAxis::LinX,
+            UrdfJointKind::Fixed => continue,
+        };
+
+        for mut rapier_context_joints in q_rapier_context_joints.iter_mut() {
+            let Some((multibody, link_id)) = rapier_context_joints
+                .multibody_joints
+                .get_mut(urdf_joint.handle)
+            else {
+                continue;
+            };
+
+            let Some(link) = multibody.link_mut(link_id) else {
+                continue;
+            };
+
+            link.joint.data.set_motor(
+                axis,
+                event.target_pos,
+                event.target_vel,
+                event.stiffness,
+                event.damping,
+            );
+        }
+    }
+}
+
+pub(crate) fn update_joint_state(
+    mut q_joints: Query<(&UrdfJoint, &UrdfRobotRigidBodyHandle, &mut JointState)>,
+    q_rapier_rigid_body_set: Query<&RapierRigidBodySet>,
+) {
+    for (urdf_joint, child_handle, mut state) in q_joints.iter_mut() {
+        for rapier_rigid_body_set in q_rapier_rigid_body_set.iter() {
+            let Some(child_body) = rapier_rigid_body_set.bodies.get(child_handle.0) else {
+                continue;
+            };
+            let Some(parent_body) = rapier_rigid_body_set.bodies.get(urdf_joint.parent_body)
+            else {
+                continue;
+            };
+
+            if urdf_joint.kind == UrdfJointKind::Fixed {
+                continue;
+            }
+
+            let child_rot = child_body.position().rotation;
+            let parent_rot = parent_body.position().rotation;
+            let child_quat = Quat::from_array([child_rot.i, child_rot.j, child_rot.k, child_rot.w]);
+            let parent_quat =
+                Quat::from_array([parent_rot.i, parent_rot.j, parent_rot.k, parent_rot.w]);
+
+            if urdf_joint.kind == UrdfJointKind::Prismatic {
+                let child_pos = child_body.position().translation;
+                let parent_pos = parent_body.position().translation;
+                let relative_pos = parent_quat.inverse()
+                    * Vec3::new(
+                        child_pos.x - parent_pos.x,
+                        child_pos.y - parent_pos.y,
+                        child_pos.z - parent_pos.z,
+                    );
+                state.position = relative_pos.dot(urdf_joint.axis) - urdf_joint.origin_offset;
+
+                let child_vel = child_body.linvel();
+                let parent_vel = parent_body.linvel();
+                let relative_vel = parent_quat.inverse()
+                    * Vec3::new(child_vel.x - parent_vel.x, child_vel.y - parent_vel.y, child_vel.z - parent_vel.z);
+                state.velocity = relative_vel.dot(urdf_joint.axis);
+            } else {
+                let relative_rot = parent_quat.inverse() * child_quat;
+                let (axis, angle) = relative_rot.to_axis_angle();
+                state.position = angle * axis.dot(urdf_joint.axis).signum();
+
+                let child_vel = child_body.angvel();
+                let parent_vel = parent_body.angvel();
+                let relative_vel = parent_quat.inverse()
+                    * Vec3::new(child_vel.x - parent_vel.x, child_vel.y - parent_vel.y, child_vel.z - parent_vel.z);
+                state.velocity = relative_vel.dot(urdf_joint.axis);
+            }
+        }
+    }
+}
+
+pub(crate) fn compute_mesh_colliders(
+    mut commands: Commands,
+    meshes: Res<Assets<Mesh>>,
+    q_pending: Query<(Entity, &Mesh3d, &MeshColliderPending)>,
+    mut q_rapier_context: Query<(&mut RapierContextColliders, &mut RapierRigidBodySet)>,
+) {
+    for (entity, mesh_3d, pending) in q_pending.iter() {
+        let Some(mesh) = meshes.get(&mesh_3d.0) else {
+            continue;
+        };
+
+        let bevy_collider = Collider::from_bevy_mesh(mesh, &pending.compute_shape);
+        commands.entity(entity).remove::<MeshColliderPending>();
+
+        let Some(bevy_collider) = bevy_collider else {
+            continue;
+        };
+
+        for (mut collider_set, mut rigid_body_set) in q_rapier_context.iter_mut() {
+            if !rigid_body_set.bodies.contains(pending.body) {
+                continue;
+            }
+
+            collider_set.colliders.insert_with_parent(
+                ColliderBuilder::new(bevy_collider.raw.clone()).build(),
+                pending.body,
+                &mut rigid_body_set.bodies,
+            );
+            break;
+        }
+    }
+}
@@ -1,13 +1,19 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::RapierRigidBodySet;
-use rapier3d::prelude::Collider;
+use rapier3d::prelude::{
+    Collider, ColliderBuilder, ColliderSet, Isometry, MultibodyJointHandle, MultibodyJointSet,
+    Real, RigidBodyHandle, RigidBodySet,
+};
+use rapier3d_urdf::UrdfMultibodyOptions;
 use urdf_rs::{Geometry, Pose};
 
 use crate::{
     events::{
-        handle_load_robot, handle_spawn_robot, handle_wait_robot_loaded, LoadRobot, RobotLoaded,
+        compute_mesh_colliders, handle_load_robot, handle_set_joint_motor, handle_spawn_robot,
+        handle_wait_robot_loaded, update_joint_state, LoadRobot, RobotLoaded, SetJointMotor,
         SpawnRobot, UrdfRobotRigidBodyHandle, WaitRobotLoaded,
     },
+    sdf_asset_loader,
     urdf_asset_loader::{self, UrdfAsset},
 };
 pub struct UrdfPlugin;
@@ -15,73 +21,344 @@ pub struct UrdfPlugin;
 impl Plugin for UrdfPlugin {
     fn build(&self, app: &mut App) {
         app.init_asset_loader::<urdf_asset_loader::RpyAssetLoader>()
+            .init_asset_loader::<sdf_asset_loader::SdfAssetLoader>()
             .add_event::<SpawnRobot>()
             .add_event::<WaitRobotLoaded>()
             .add_event::<LoadRobot>()
             .add_event::<RobotLoaded>()
-            .add_systems(Update, sync_robot_geometry)
-            .add_systems(
-                Update,
-                (
-                    handle_spawn_robot,
-                    handle_load_robot,
-                    handle_wait_robot_loaded,
-                ),
-            )
+            .add_event::<SetJointMotor>()
+            .add_systems(Update, (handle_load_robot, handle_wait_robot_loaded))
             .init_asset::<urdf_asset_loader::UrdfAsset>();
+
+        // The backend is an ordinary cargo feature, not a generic plugin
+        // parameter: `UrdfRobotRigidBodyHandle`/`UrdfJoint` are concrete
+        // rapier types, so the two paths spawn and drive robots through
+        // entirely different system sets rather than one system generic
+        // over `UrdfPhysicsBackend`.
+        #[cfg(not(feature = "avian"))]
+        app.add_systems(
+            Update,
+            (
+                handle_spawn_robot,
+                sync_robot_geometry,
+                update_joint_state,
+                handle_set_joint_motor,
+                compute_mesh_colliders,
+            ),
+        );
+
+        #[cfg(feature = "avian")]
+        app.add_systems(Update, avian_backend::handle_spawn_robot_avian);
     }
 }
 
 pub fn extract_robot_geometry(
     robot: &UrdfAsset,
-) -> Vec<(usize, Option<Geometry>, Pose, Option<Collider>)> {
-    let mut result: Vec<(usize, Option<Geometry>, Pose, Option<Collider>)> = Vec::new();
+) -> Vec<(usize, Option<Geometry>, Pose, Vec<Collider>)> {
+    let mut result: Vec<(usize, Option<Geometry>, Pose, Vec<Collider>)> = Vec::new();
     for (i, link) in robot.robot.links.iter().enumerate() {
         let colliders = robot.urdf_robot.links[i].colliders.clone();
-        let collider = if colliders.len() == 1 {
-            Some(colliders[0].clone())
-        } else {
-            None
-        };
 
         let geometry = if !link.visual.is_empty() {
             Some(link.visual[0].geometry.clone())
         } else {
             None
         };
+
+        let colliders = if colliders.is_empty() {
+            geometry
+                .as_ref()
+                .and_then(collider_from_geometry)
+                .into_iter()
+                .collect()
+        } else {
+            colliders
+        };
+
         let inertia_origin = link.inertial.origin.clone();
 
-        result.push((i, geometry.clone(), inertia_origin.clone(), collider));
+        result.push((i, geometry.clone(), inertia_origin.clone(), colliders));
     }
 
     result
 }
 
+/// Builds a collider matching a link's visual primitive for links that don't
+/// define their own `<collision>` geometry.
+fn collider_from_geometry(geometry: &Geometry) -> Option<Collider> {
+    match geometry {
+        // Half-extents, matching the Y/Z swap `handle_spawn_robot` applies
+        // to the visual `Cuboid` (`Cuboid::new` takes full extents, so these
+        // are its `size[0] * 2.0, size[2] * 2.0, size[1] * 2.0` halved, in
+        // the same swapped order) so the collider wireframe lines up with
+        // the mesh.
+        Geometry::Box { size } => Some(
+            ColliderBuilder::cuboid(size[0] as f32, size[2] as f32, size[1] as f32).build(),
+        ),
+        Geometry::Sphere { radius } => Some(ColliderBuilder::ball(*radius as f32).build()),
+        Geometry::Cylinder { radius, length } => {
+            Some(ColliderBuilder::cylinder(*length as f32 / 2.0, *radius as f32).build())
+        }
+        Geometry::Capsule { radius, length } => {
+            Some(ColliderBuilder::capsule_y(*length as f32 / 2.0, *radius as f32).build())
+        }
+        Geometry::Mesh { .. } => None,
+    }
+}
+
+/// Abstracts the physics engine a spawned robot's links and joints live in,
+/// so callers aren't hard-wired to a single backend's handle/world types.
+/// [`RapierBackend`] is used by default; enabling the `avian` cargo feature
+/// switches [`UrdfPlugin`]'s `build` over to `avian_backend::AvianBackend`
+/// instead.
+pub trait UrdfPhysicsBackend {
+    /// Per-link handle returned by [`Self::insert_robot`].
+    type BodyHandle: Copy + Send + Sync + 'static;
+    /// Per-joint handle returned by [`Self::insert_robot`].
+    type JointHandle: Copy + Send + Sync + 'static;
+    /// Mutable physics state `insert_robot` inserts bodies/joints into.
+    type InsertContext<'a>;
+    /// Physics state `body_transform` reads link poses from.
+    type Bodies<'a>;
+
+    /// Inserts every link of a parsed robot into the physics world and
+    /// returns one handle per link and one handle per joint, both in the
+    /// same order as `urdf.robot.links`/`urdf.robot.joints`.
+    fn insert_robot(
+        ctx: &mut Self::InsertContext<'_>,
+        urdf: &UrdfAsset,
+    ) -> (Vec<Self::BodyHandle>, Vec<Self::JointHandle>);
+
+    /// Reads a link's current world-space transform, already converted from
+    /// the backend's native Z-up convention into Bevy's Y-up one.
+    fn body_transform(bodies: &Self::Bodies<'_>, handle: Self::BodyHandle) -> Option<Transform>;
+}
+
+/// Converts a rapier body/collider pose (rapier's native Z-up convention)
+/// into the equivalent Bevy (Y-up) `Transform`. The one place this
+/// conversion lives; every call site that reads a rapier pose goes through
+/// here instead of re-deriving the `quat_fix` mapping.
+pub(crate) fn rapier_isometry_to_bevy_transform(pos: &Isometry<Real>) -> Transform {
+    let rapier_rot = pos.rotation;
+    let quat_fix = Quat::from_rotation_z(std::f32::consts::PI);
+    let bevy_quat =
+        quat_fix * Quat::from_array([rapier_rot.i, rapier_rot.j, rapier_rot.k, rapier_rot.w]);
+
+    let rapier_vec = Vec3::new(pos.translation.x, pos.translation.y, pos.translation.z);
+    let bevy_vec = quat_fix.mul_vec3(rapier_vec);
+
+    Transform::from_translation(bevy_vec).with_rotation(bevy_quat)
+}
+
+/// Bundles the raw rapier3d sets `insert_using_multibody_joints` writes into.
+pub struct RapierInsertContext<'a> {
+    pub bodies: &'a mut RigidBodySet,
+    pub colliders: &'a mut ColliderSet,
+    pub multibody_joints: &'a mut MultibodyJointSet,
+}
+
+pub struct RapierBackend;
+
+impl UrdfPhysicsBackend for RapierBackend {
+    type BodyHandle = RigidBodyHandle;
+    type JointHandle = Option<MultibodyJointHandle>;
+    type InsertContext<'a> = RapierInsertContext<'a>;
+    type Bodies<'a> = RigidBodySet;
+
+    fn insert_robot(
+        ctx: &mut Self::InsertContext<'_>,
+        urdf: &UrdfAsset,
+    ) -> (Vec<Self::BodyHandle>, Vec<Self::JointHandle>) {
+        let handles = urdf.urdf_robot.clone().insert_using_multibody_joints(
+            ctx.bodies,
+            ctx.colliders,
+            ctx.multibody_joints,
+            UrdfMultibodyOptions::DISABLE_SELF_CONTACTS,
+        );
+
+        let body_handles = handles.links.iter().map(|link| link.body).collect();
+        (body_handles, handles.joints)
+    }
+
+    fn body_transform(bodies: &Self::Bodies<'_>, handle: Self::BodyHandle) -> Option<Transform> {
+        let body = bodies.get(handle)?;
+        Some(rapier_isometry_to_bevy_transform(body.position()))
+    }
+}
+
 fn sync_robot_geometry(
     mut q_rapier_robot_bodies: Query<(Entity, &mut Transform, &mut UrdfRobotRigidBodyHandle)>,
     q_rapier_rigid_body_set: Query<(&RapierRigidBodySet,)>,
 ) {
     for rapier_rigid_body_set in q_rapier_rigid_body_set.iter() {
         for (_, mut transform, body_handle) in q_rapier_robot_bodies.iter_mut() {
-            if let Some(robot_body) = rapier_rigid_body_set.0.bodies.get(body_handle.0) {
-                let rapier_pos = robot_body.position();
+            if let Some(new_transform) =
+                RapierBackend::body_transform(&rapier_rigid_body_set.0.bodies, body_handle.0)
+            {
+                *transform = new_transform;
+            }
+        }
+    }
+}
 
-                let rapier_rot = rapier_pos.rotation;
+/// Drives spawned robots through Avian's articulated-body/joint APIs instead
+/// of rapier3d: each link becomes a dynamic rigid body with a collider
+/// derived from its URDF geometry, and each URDF joint becomes the matching
+/// Avian joint component (`RevoluteJoint`/`PrismaticJoint`/`FixedJoint`)
+/// connecting the parent and child bodies. Enabling the `avian` feature
+/// swaps [`UrdfPlugin`] over to this path entirely (see its `build`): Avian
+/// writes `Transform` onto the entities it simulates itself, so there's no
+/// rapier-style sync system to register alongside it. Joint actuation is
+/// rapier-only for now — [`SetJointMotor`](crate::events::SetJointMotor) and
+/// [`JointState`](crate::events::JointState) have no Avian equivalent yet.
+#[cfg(feature = "avian")]
+mod avian_backend {
+    use super::{rapier_isometry_to_bevy_transform, UrdfAsset, UrdfPhysicsBackend};
+    use crate::events::{SpawnRobot, WaitRobotLoaded};
+    use avian3d::prelude::*;
+    use bevy::prelude::*;
 
-                let quat_fix = Quat::from_rotation_z(std::f32::consts::PI);
-                let bevy_quat = quat_fix
-                    * Quat::from_array([rapier_rot.i, rapier_rot.j, rapier_rot.k, rapier_rot.w]);
+    pub struct AvianBackend;
 
-                let rapier_vec = Vec3::new(
-                    rapier_pos.translation.x,
-                    rapier_pos.translation.y,
-                    rapier_pos.translation.z,
-                );
-                let bevy_vec = quat_fix.mul_vec3(rapier_vec);
-                // bevy_vec.y *= -1.0;
+    /// The `avian`-feature counterpart to
+    /// [`handle_spawn_robot`](crate::events::handle_spawn_robot): spawns
+    /// through [`AvianBackend::insert_robot`] instead of the rapier3d
+    /// multibody sets. `insert_robot` takes `&mut World` (there's no single
+    /// lifetime that can name both halves of `Commands<'w, 's>`), so this
+    /// defers the actual spawn to a one-off exclusive-access command instead
+    /// of running as an exclusive system itself.
+    pub(crate) fn handle_spawn_robot_avian(
+        urdf_assets: Res<Assets<UrdfAsset>>,
+        mut er_spawn_robot: EventReader<SpawnRobot>,
+        mut ew_wait_robot_loaded: EventWriter<WaitRobotLoaded>,
+        mut commands: Commands,
+    ) {
+        for event in er_spawn_robot.read() {
+            let Some(urdf) = urdf_assets.get(&event.handle) else {
+                ew_wait_robot_loaded.send(WaitRobotLoaded {
+                    handle: event.handle.clone(),
+                    mesh_dir: event.mesh_dir.clone(),
+                    mesh_collider_shape: event.mesh_collider_shape.clone(),
+                });
+                continue;
+            };
 
-                *transform = Transform::from_translation(bevy_vec).with_rotation(bevy_quat);
+            let urdf = UrdfAsset {
+                robot: urdf.robot.clone(),
+                urdf_robot: urdf.urdf_robot.clone(),
+            };
+            commands.queue(move |world: &mut World| {
+                AvianBackend::insert_robot(world, &urdf);
+            });
+        }
+    }
+
+    /// Builds an Avian collider matching a link's geometry, mirroring
+    /// [`super::collider_from_geometry`] for the rapier backend.
+    fn avian_collider_from_geometry(geometry: &urdf_rs::Geometry) -> Option<Collider> {
+        match geometry {
+            urdf_rs::Geometry::Box { size } => Some(Collider::cuboid(
+                size[0] as f32,
+                size[1] as f32,
+                size[2] as f32,
+            )),
+            urdf_rs::Geometry::Sphere { radius } => Some(Collider::sphere(*radius as f32)),
+            urdf_rs::Geometry::Cylinder { radius, length } => {
+                Some(Collider::cylinder(*radius as f32, *length as f32))
+            }
+            urdf_rs::Geometry::Capsule { radius, length } => {
+                Some(Collider::capsule(*radius as f32, *length as f32))
             }
+            urdf_rs::Geometry::Mesh { .. } => None,
+        }
+    }
+
+    impl UrdfPhysicsBackend for AvianBackend {
+        type BodyHandle = Entity;
+        /// The entity the joint's Avian joint component is attached to.
+        type JointHandle = Entity;
+        type InsertContext<'a> = World;
+        type Bodies<'a> = World;
+
+        fn insert_robot(
+            ctx: &mut Self::InsertContext<'_>,
+            urdf: &UrdfAsset,
+        ) -> (Vec<Self::BodyHandle>, Vec<Self::JointHandle>) {
+            let body_handles: Vec<Entity> = urdf
+                .urdf_robot
+                .links
+                .iter()
+                .zip(urdf.robot.links.iter())
+                .map(|(rapier_link, link)| {
+                    let transform = rapier_isometry_to_bevy_transform(rapier_link.body.position());
+                    let collider = link
+                        .collision
+                        .first()
+                        .and_then(|collision| avian_collider_from_geometry(&collision.geometry))
+                        .or_else(|| {
+                            link.visual
+                                .first()
+                                .and_then(|visual| avian_collider_from_geometry(&visual.geometry))
+                        });
+
+                    let mut entity = ctx.spawn((RigidBody::Dynamic, transform));
+                    if let Some(collider) = collider {
+                        entity.insert(collider);
+                    }
+                    entity.id()
+                })
+                .collect();
+
+            let link_index_by_name: std::collections::HashMap<&str, usize> = urdf
+                .robot
+                .links
+                .iter()
+                .enumerate()
+                .map(|(index, link)| (link.name.as_str(), index))
+                .collect();
+
+            let joint_handles = urdf
+                .robot
+                .joints
+                .iter()
+                .filter_map(|joint| {
+                    let parent_index = *link_index_by_name.get(joint.parent.link.as_str())?;
+                    let child_index = *link_index_by_name.get(joint.child.link.as_str())?;
+                    let parent_entity = body_handles[parent_index];
+                    let child_entity = body_handles[child_index];
+
+                    let axis = Vec3::new(
+                        joint.axis.xyz[0] as f32,
+                        joint.axis.xyz[1] as f32,
+                        joint.axis.xyz[2] as f32,
+                    )
+                    .normalize_or_zero();
+
+                    let joint_entity = match joint.joint_type {
+                        urdf_rs::JointType::Revolute | urdf_rs::JointType::Continuous => ctx
+                            .spawn(
+                                RevoluteJoint::new(parent_entity, child_entity)
+                                    .with_aligned_axis(axis),
+                            )
+                            .id(),
+                        urdf_rs::JointType::Prismatic => ctx
+                            .spawn(
+                                PrismaticJoint::new(parent_entity, child_entity)
+                                    .with_free_axis(axis),
+                            )
+                            .id(),
+                        _ => ctx.spawn(FixedJoint::new(parent_entity, child_entity)).id(),
+                    };
+
+                    Some(joint_entity)
+                })
+                .collect();
+
+            (body_handles, joint_handles)
+        }
+
+        fn body_transform(bodies: &Self::Bodies<'_>, handle: Self::BodyHandle) -> Option<Transform> {
+            bodies.get::<Transform>(handle).copied()
         }
     }
 }
@@ -0,0 +1,438 @@
+use bevy::asset::{io::Reader, AssetLoader, LoadContext};
+use rapier3d_urdf::{UrdfRobot, UrdfRobotOptions};
+use thiserror::Error;
+use urdf_rs::{
+    Axis, Collision, Geometry, Inertial, Joint, JointLimit, JointType, Link, LinkName, Mass,
+    Material, Mimic, Pose, Robot, SafetyController, Visual,
+};
+
+use crate::urdf_asset_loader::{RpyAssetLoaderSettings, UrdfAsset};
+
+/// Loads Gazebo-style SDF models into the same [`UrdfAsset`] representation
+/// [`crate::urdf_asset_loader::RpyAssetLoader`] produces for URDF, so
+/// `LoadRobot`/`SpawnRobot` work unchanged regardless of source format. Only
+/// `<model>`/`<link>`/`<joint>`/`<geometry>` are translated; `<include>`d
+/// models are not resolved and are skipped with a warning.
+#[derive(Default)]
+pub struct SdfAssetLoader;
+
+#[derive(Debug, Error)]
+pub enum SdfAssetLoaderError {
+    #[error("could not read SDF file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse SDF: {0}")]
+    Xml(#[from] roxmltree::Error),
+    #[error("SDF file has no <model> element")]
+    MissingModel,
+}
+
+impl AssetLoader for SdfAssetLoader {
+    type Asset = UrdfAsset;
+    type Settings = RpyAssetLoaderSettings;
+    type Error = SdfAssetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &RpyAssetLoaderSettings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = String::from_utf8_lossy(&bytes);
+        let document = roxmltree::Document::parse(&text)?;
+
+        let robot = sdf_document_to_robot(&document)?;
+        let urdf_robot = UrdfRobot::from_robot(&robot, UrdfRobotOptions::default());
+
+        Ok(UrdfAsset { robot, urdf_robot })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sdf"]
+    }
+}
+
+fn sdf_document_to_robot(document: &roxmltree::Document) -> Result<Robot, SdfAssetLoaderError> {
+    let model = document
+        .descendants()
+        .find(|node| node.has_tag_name("model"))
+        .ok_or(SdfAssetLoaderError::MissingModel)?;
+
+    for include in model.children().filter(|node| node.has_tag_name("include")) {
+        let uri = include
+            .children()
+            .find(|node| node.has_tag_name("uri"))
+            .and_then(|node| node.text())
+            .unwrap_or("<unknown>");
+        bevy::log::warn!("SdfAssetLoader: skipping unresolved <include> model '{uri}'");
+    }
+
+    let name = model.attribute("name").unwrap_or("sdf_robot").to_string();
+
+    let link_nodes: Vec<roxmltree::Node> = model
+        .children()
+        .filter(|node| node.has_tag_name("link"))
+        .collect();
+
+    // Each link's own `<pose>` is relative to the model, not to its parent
+    // joint; keep it around (by name) so joint origins can be synthesized
+    // from it below instead of from the joint node's `<pose>` alone.
+    let link_poses: std::collections::HashMap<&str, SdfTransform> = link_nodes
+        .iter()
+        .map(|node| {
+            (
+                node.attribute("name").unwrap_or_default(),
+                SdfTransform::from_pose(&sdf_pose(*node)),
+            )
+        })
+        .collect();
+
+    let links = link_nodes.iter().map(|node| sdf_link_to_urdf_link(*node)).collect();
+
+    let joints = model
+        .children()
+        .filter(|node| node.has_tag_name("joint"))
+        .filter_map(|node| sdf_joint_to_urdf_joint(node, &link_poses))
+        .collect();
+
+    Ok(Robot {
+        name,
+        links,
+        joints,
+        materials: Vec::new(),
+    })
+}
+
+fn sdf_link_to_urdf_link(link: roxmltree::Node) -> Link {
+    let name = link.attribute("name").unwrap_or_default().to_string();
+
+    let visual = link
+        .children()
+        .filter(|node| node.has_tag_name("visual"))
+        .filter_map(|node| {
+            let geometry = node
+                .children()
+                .find(|c| c.has_tag_name("geometry"))
+                .and_then(sdf_geometry)?;
+            Some(Visual {
+                name: None,
+                origin: sdf_pose(node),
+                geometry,
+                material: None::<Material>,
+            })
+        })
+        .collect();
+
+    let collision = link
+        .children()
+        .filter(|node| node.has_tag_name("collision"))
+        .filter_map(|node| {
+            let geometry = node
+                .children()
+                .find(|c| c.has_tag_name("geometry"))
+                .and_then(sdf_geometry)?;
+            Some(Collision {
+                name: None,
+                origin: sdf_pose(node),
+                geometry,
+            })
+        })
+        .collect();
+
+    let inertial = link
+        .children()
+        .find(|node| node.has_tag_name("inertial"))
+        .map(|node| Inertial {
+            origin: sdf_pose(node),
+            mass: Mass {
+                value: sdf_child_f64(node, "mass").unwrap_or(1.0),
+            },
+            ..Default::default()
+        })
+        .unwrap_or(Inertial {
+            origin: Pose::default(),
+            mass: Mass { value: 1.0 },
+            inertia: Default::default(),
+        });
+
+    Link {
+        name,
+        inertial,
+        visual,
+        collision,
+    }
+}
+
+fn sdf_joint_to_urdf_joint(
+    joint: roxmltree::Node,
+    link_poses: &std::collections::HashMap<&str, SdfTransform>,
+) -> Option<Joint> {
+    let name = joint.attribute("name")?.to_string();
+    let joint_type = match joint.attribute("type")? {
+        "revolute" => JointType::Revolute,
+        "continuous" => JointType::Continuous,
+        "prismatic" => JointType::Prismatic,
+        "fixed" => JointType::Fixed,
+        "floating" => JointType::Floating,
+        "planar" => JointType::Planar,
+        _ => JointType::Fixed,
+    };
+
+    let parent = joint
+        .children()
+        .find(|node| node.has_tag_name("parent"))
+        .and_then(|node| node.text())
+        .unwrap_or_default()
+        .to_string();
+    let child = joint
+        .children()
+        .find(|node| node.has_tag_name("child"))
+        .and_then(|node| node.text())
+        .unwrap_or_default()
+        .to_string();
+
+    let axis = joint
+        .children()
+        .find(|node| node.has_tag_name("axis"))
+        .and_then(|node| node.children().find(|c| c.has_tag_name("xyz")))
+        .and_then(|node| node.text())
+        .map(sdf_parse_xyz)
+        .unwrap_or([1.0, 0.0, 0.0]);
+
+    let limit = joint
+        .children()
+        .find(|node| node.has_tag_name("axis"))
+        .and_then(|node| node.children().find(|c| c.has_tag_name("limit")))
+        .map(|node| JointLimit {
+            lower: sdf_child_f64(node, "lower").unwrap_or(0.0),
+            upper: sdf_child_f64(node, "upper").unwrap_or(0.0),
+            effort: sdf_child_f64(node, "effort").unwrap_or(0.0),
+            velocity: sdf_child_f64(node, "velocity").unwrap_or(0.0),
+        })
+        .unwrap_or_default();
+
+    // SDF gives each link's pose relative to the model and each joint's
+    // pose relative to its *child* link (the default `<joint><pose>`
+    // frame), while URDF wants the joint's origin relative to the
+    // *parent* link. Compose them: model<-joint = (model<-child) *
+    // (child<-joint), then express that in the parent's frame by
+    // prepending (parent<-model) = (model<-parent)^-1.
+    let parent_pose = link_poses
+        .get(parent.as_str())
+        .copied()
+        .unwrap_or_else(SdfTransform::identity);
+    let child_pose = link_poses
+        .get(child.as_str())
+        .copied()
+        .unwrap_or_else(SdfTransform::identity);
+    let joint_pose_in_child = SdfTransform::from_pose(&sdf_pose(joint));
+    let joint_pose_in_model = child_pose.then(&joint_pose_in_child);
+    let origin = parent_pose.inverse().then(&joint_pose_in_model).to_pose();
+
+    Some(Joint {
+        name,
+        joint_type,
+        origin,
+        parent: LinkName { link: parent },
+        child: LinkName { link: child },
+        axis: Axis { xyz: axis },
+        limit,
+        mimic: None::<Mimic>,
+        safety_controller: None::<SafetyController>,
+    })
+}
+
+/// A rigid transform (rotation + translation), used to compose SDF poses —
+/// which are given relative to the model or to a joint's child link — into
+/// the parent-relative origin URDF expects for `<joint><origin>`.
+#[derive(Clone, Copy)]
+struct SdfTransform {
+    translation: [f64; 3],
+    rotation: [[f64; 3]; 3],
+}
+
+impl SdfTransform {
+    fn identity() -> Self {
+        Self {
+            translation: [0.0; 3],
+            rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        }
+    }
+
+    /// Builds the transform an SDF `<pose>` (`x y z roll pitch yaw`, fixed-axis)
+    /// describes: `R = Rz(yaw) * Ry(pitch) * Rx(roll)`.
+    fn from_pose(pose: &Pose) -> Self {
+        let [roll, pitch, yaw] = pose.rpy;
+        let (sr, cr) = roll.sin_cos();
+        let (sp, cp) = pitch.sin_cos();
+        let (sy, cy) = yaw.sin_cos();
+
+        let rotation = [
+            [cy * cp, cy * sp * sr - sy * cr, cy * sp * cr + sy * sr],
+            [sy * cp, sy * sp * sr + cy * cr, sy * sp * cr - cy * sr],
+            [-sp, cp * sr, cp * cr],
+        ];
+
+        Self {
+            translation: pose.xyz,
+            rotation,
+        }
+    }
+
+    fn mul_vec(&self, v: [f64; 3]) -> [f64; 3] {
+        std::array::from_fn(|i| {
+            self.rotation[i][0] * v[0] + self.rotation[i][1] * v[1] + self.rotation[i][2] * v[2]
+        })
+    }
+
+    /// Composes `self * other`: if `self` is `a<-b` and `other` is `b<-c`,
+    /// the result is `a<-c`.
+    fn then(&self, other: &Self) -> Self {
+        let rotated = self.mul_vec(other.translation);
+        let translation = std::array::from_fn(|i| self.translation[i] + rotated[i]);
+
+        let mut rotation = [[0.0; 3]; 3];
+        for (i, row) in rotation.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = self.rotation[i][0] * other.rotation[0][j]
+                    + self.rotation[i][1] * other.rotation[1][j]
+                    + self.rotation[i][2] * other.rotation[2][j];
+            }
+        }
+
+        Self {
+            translation,
+            rotation,
+        }
+    }
+
+    /// Inverse of a rigid transform: the rotation is orthonormal, so its
+    /// inverse is its transpose.
+    fn inverse(&self) -> Self {
+        let mut rotation = [[0.0; 3]; 3];
+        for (i, row) in rotation.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = self.rotation[j][i];
+            }
+        }
+
+        let translation = std::array::from_fn(|i| {
+            -(rotation[i][0] * self.translation[0]
+                + rotation[i][1] * self.translation[1]
+                + rotation[i][2] * self.translation[2])
+        });
+
+        Self {
+            translation,
+            rotation,
+        }
+    }
+
+    /// Recovers fixed-axis roll/pitch/yaw from the rotation matrix, the
+    /// inverse of [`Self::from_pose`]'s construction.
+    fn to_rpy(&self) -> [f64; 3] {
+        let pitch = (-self.rotation[2][0]).asin();
+        let (roll, yaw) = if pitch.cos().abs() > 1e-6 {
+            (
+                self.rotation[2][1].atan2(self.rotation[2][2]),
+                self.rotation[1][0].atan2(self.rotation[0][0]),
+            )
+        } else {
+            // Gimbal lock: roll and yaw aren't independently recoverable;
+            // fold the whole rotation about the vertical axis into yaw.
+            (0.0, (-self.rotation[0][1]).atan2(self.rotation[1][1]))
+        };
+
+        [roll, pitch, yaw]
+    }
+
+    fn to_pose(&self) -> Pose {
+        Pose {
+            xyz: self.translation,
+            rpy: self.to_rpy(),
+        }
+    }
+}
+
+fn sdf_geometry(geometry: roxmltree::Node) -> Option<Geometry> {
+    let shape = geometry.children().find(|node| node.is_element())?;
+    match shape.tag_name().name() {
+        "box" => {
+            let size = shape
+                .children()
+                .find(|node| node.has_tag_name("size"))
+                .and_then(|node| node.text())
+                .map(sdf_parse_xyz)
+                .unwrap_or([1.0, 1.0, 1.0]);
+            Some(Geometry::Box { size })
+        }
+        "sphere" => Some(Geometry::Sphere {
+            radius: sdf_child_f64(shape, "radius").unwrap_or(0.5),
+        }),
+        "cylinder" => Some(Geometry::Cylinder {
+            radius: sdf_child_f64(shape, "radius").unwrap_or(0.5),
+            length: sdf_child_f64(shape, "length").unwrap_or(1.0),
+        }),
+        "mesh" => {
+            let filename = shape
+                .children()
+                .find(|node| node.has_tag_name("uri"))
+                .and_then(|node| node.text())
+                .unwrap_or_default()
+                .to_string();
+            Some(Geometry::Mesh {
+                filename,
+                scale: None,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn sdf_pose(node: roxmltree::Node) -> Pose {
+    let Some(text) = node
+        .children()
+        .find(|n| n.has_tag_name("pose"))
+        .and_then(|n| n.text())
+    else {
+        return Pose::default();
+    };
+
+    let values: Vec<f64> = text
+        .split_whitespace()
+        .filter_map(|v| v.parse().ok())
+        .collect();
+
+    let xyz = [
+        *values.first().unwrap_or(&0.0),
+        *values.get(1).unwrap_or(&0.0),
+        *values.get(2).unwrap_or(&0.0),
+    ];
+    let rpy = [
+        *values.get(3).unwrap_or(&0.0),
+        *values.get(4).unwrap_or(&0.0),
+        *values.get(5).unwrap_or(&0.0),
+    ];
+
+    Pose { xyz, rpy }
+}
+
+fn sdf_parse_xyz(text: &str) -> [f64; 3] {
+    let values: Vec<f64> = text
+        .split_whitespace()
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    [
+        *values.first().unwrap_or(&0.0),
+        *values.get(1).unwrap_or(&0.0),
+        *values.get(2).unwrap_or(&0.0),
+    ]
+}
+
+fn sdf_child_f64(node: roxmltree::Node, tag: &str) -> Option<f64> {
+    node.children()
+        .find(|n| n.has_tag_name(tag))
+        .and_then(|n| n.text())
+        .and_then(|text| text.parse().ok())
+}